@@ -0,0 +1,58 @@
+//! `next_token` 的第一层分派：给每个 ASCII 字节打一个“大类”标签。
+//!
+//! 原来的写法是对 `char` 做一个大 `match`，每扫一个 token 都要重新走一遍这个
+//! match 的分支顺序。这里把“这个字节属于哪一类 token”预先算进一张 256 项的
+//! 查找表里，`next_token` 只需要做一次数组索引就能知道该往哪个扫描函数分派，
+//! 非 ASCII 字节（多字节 UTF-8 的前导字节）才会落到较慢的 `is_alphabetic` 路径。
+
+/// 一个 ASCII 字节所属的词法大类。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass {
+    /// `{ } ( ) ^ & . , + * - : = ! < > ;` 等标点/操作符起始字符
+    Punct,
+    /// `0..=9`
+    Digit,
+    /// 标识符/关键字起始字符：字母或 `_`
+    IdentStart,
+    /// `"`
+    Quote,
+    /// `\n`
+    Newline,
+    /// 空格、`\t`、`\r`
+    Whitespace,
+    /// `/`，单独一类是因为它既可能是除号，也可能引出注释
+    Slash,
+    /// 其余字节：ASCII 范围内没有对应语义的字符
+    Illegal,
+}
+
+const fn classify_byte(b: u8) -> ByteClass {
+    match b {
+        b' ' | b'\t' | b'\r' => ByteClass::Whitespace,
+        b'\n' => ByteClass::Newline,
+        b'"' => ByteClass::Quote,
+        b'/' => ByteClass::Slash,
+        b'0'..=b'9' => ByteClass::Digit,
+        b'a'..=b'z' | b'A'..=b'Z' | b'_' => ByteClass::IdentStart,
+        b'{' | b'}' | b'(' | b')' | b'^' | b'&' | b'.' | b',' | b'+' | b'-' | b'*' | b':'
+        | b'=' | b'!' | b'<' | b'>' | b';' => ByteClass::Punct,
+        _ => ByteClass::Illegal,
+    }
+}
+
+const fn build_table() -> [ByteClass; 256] {
+    let mut table = [ByteClass::Illegal; 256];
+    let mut byte = 0usize;
+    while byte < 256 {
+        table[byte] = classify_byte(byte as u8);
+        byte += 1;
+    }
+    table
+}
+
+const BYTE_CLASS_TABLE: [ByteClass; 256] = build_table();
+
+/// 给一个 ASCII 字节分类。调用方需要保证 `byte` 来自一个 ASCII 字符。
+pub fn classify(byte: u8) -> ByteClass {
+    BYTE_CLASS_TABLE[byte as usize]
+}
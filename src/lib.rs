@@ -1,20 +1,21 @@
+mod cursor;
+mod dispatch;
 mod tokens;
 #[cfg(test)]
 mod tests;
 
+use cursor::Cursor;
+use dispatch::ByteClass;
 use nyanc_core::errors::{CompilerError, LexerError, LexerErrorKind};
 use nyanc_core::{FileId, Span};
 use reporter::DiagnosticsEngine;
 use tokens::{Token, TokenType};
-// 引入标准库的 Peekable 迭代器，这是我们实现预读功能的核心
-use std::iter::Peekable;
-use std::str::Chars;
 
 /// Lexer 负责将源代码字符串分解为 Token 序列。
 pub struct Lexer<'a> {
     diagnostics: &'a DiagnosticsEngine,
-    source: &'a str,            // 完整的源代码引用，用于从 Span 中提取 lexeme
-    chars: Peekable<Chars<'a>>, // 带有预读能力的字符迭代器
+    source: &'a str,   // 完整的源代码引用，用于从 Span 中提取 lexeme
+    chars: Cursor<'a>, // 支持多字符前瞻（peek_nth）和回退的字符游标
 
     file_id: FileId, // 当前正在处理的文件 ID
 
@@ -26,8 +27,20 @@ pub struct Lexer<'a> {
 
     /// 当前行号 (从 1 开始)
     line: u32,
-    /// 当前 Token 在当前行中的起始列号 (从 1 开始)
+    /// 当前列号 (从 1 开始)
     column: u32,
+    /// 当前正在扫描的 Token 的起始行号，`make_token` 用它而不是 `line`，
+    /// 因为多字符 token 扫描完时 `line`/`column` 已经前进到了 token 末尾
+    start_line: u32,
+    /// 当前正在扫描的 Token 的起始列号
+    start_column: u32,
+
+    /// 是否产出注释等 trivia token，而不是像空白一样直接跳过。
+    /// 供格式化 / 高亮等需要保留原始文本的工具开启。
+    emit_trivia: bool,
+
+    /// 本次扫描过程中累积的错误，供不持有 `DiagnosticsEngine` 的调用方使用（见 `tokenize`）。
+    errors: Vec<LexerError>,
 }
 
 impl<'a> Lexer<'a> {
@@ -35,22 +48,63 @@ impl<'a> Lexer<'a> {
         Self {
             diagnostics,
             source,
-            chars: source.chars().peekable(),
+            chars: Cursor::new(source),
             file_id,
             current_pos: 0,
             start_pos: 0,
             line: 1,
             column: 1,
+            start_line: 1,
+            start_column: 1,
+            emit_trivia: false,
+            errors: Vec::new(),
         }
     }
 
+    /// 开启 trivia 模式：注释会作为 `TokenType::Comment` token 返回，而不是被静默跳过。
+    pub fn with_trivia(mut self, enabled: bool) -> Self {
+        self.emit_trivia = enabled;
+        self
+    }
+
+    /// 工具函数：同时上报给共享的 `DiagnosticsEngine`，并记录到本地的 `errors`，
+    /// 这样 `tokenize` 也能把错误交还给不持有 `DiagnosticsEngine` 的调用方。
+    fn report_error(&mut self, err: LexerError) {
+        self.diagnostics.add_error(CompilerError::Lexer(err.clone()));
+        self.errors.push(err);
+    }
+
+    /// 扫描整个输入并一次性返回所有 token（直到 `Eof`）以及期间累积的所有错误。
+    ///
+    /// 遇到非法字符、无效转义或多余的分号时不会中断整个扫描：扫描器记录错误后
+    /// 从下一个字符继续，从而在一趟扫描里给出尽可能多的诊断信息。未闭合的字符串
+    /// 或块注释则会一直吞到文件末尾，之后只剩下 `Eof`，不会在下一个换行处恢复。
+    pub fn tokenize(&mut self) -> (Vec<Token>, Vec<LexerError>) {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next_token();
+            let is_eof = token.kind == TokenType::Eof;
+            tokens.push(token);
+            if is_eof {
+                break;
+            }
+        }
+        (tokens, std::mem::take(&mut self.errors))
+    }
+
     /// 工具函数：消耗当前字符，并向前移动一个位置。
     /// 返回被消耗的字符。
     fn advance(&mut self) -> Option<char> {
-        match self.chars.next() {
+        match self.chars.advance() {
             Some(c) => {
                 self.current_pos += c.len_utf8(); // 支持 UTF-8
-                self.column += 1;
+                // 行列号的推进集中在这里完成：调用方不必记得在遇到 '\n' 时手动重置列号。
+                if c == '\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
+                }
                 Some(c)
             }
             None => None,
@@ -59,8 +113,12 @@ impl<'a> Lexer<'a> {
 
     /// 工具函数：预读（查看）下一个字符，但不消耗它。
     fn peek(&mut self) -> Option<char> {
-        // .peek() 返回的是 &char，我们需要拷贝它
-        self.chars.peek().copied()
+        self.chars.peek()
+    }
+
+    /// 工具函数：预读从当前位置往后数第 `n` 个字符（`n = 0` 等价于 `peek`），不消耗它。
+    fn peek_nth(&mut self, n: usize) -> Option<char> {
+        self.chars.peek_nth(n)
     }
 
     /// 工具函数：根据当前位置和 Token 类型，创建一个完整的 Token。
@@ -75,89 +133,160 @@ impl<'a> Lexer<'a> {
             kind,
             lexeme: lexeme.to_string(), // 约定 MVP 阶段使用 String
             span,
+            line: self.start_line,
+            column: self.start_column,
         }
     }
 
     /// 这是 Lexer 的心脏。它遵循“准备->标记->分派->返回”的节律。
     pub fn next_token(&mut self) -> Token {
-        // 1. 准备：跳过空白
-        self.skip_whitespace();
-
-        // 2. 标记起点
-        self.start_pos = self.current_pos;
-
-        // 3. 识别与分派
-        if let Some(c) = self.advance() {
-            match c {
-                // --- 单字符 Token ---
-                '{' => self.make_token(TokenType::LeftBrace),
-                '}' => self.make_token(TokenType::RightBrace),
-                '(' => self.make_token(TokenType::LeftParen),
-                ')' => self.make_token(TokenType::RightParen),
-                '=' => self.make_token(TokenType::Equal),
-                '^' => self.make_token(TokenType::Caret),
-                '&' => self.make_token(TokenType::Ampersand),
-                '.' => self.make_token(TokenType::Dot),
-                ',' => self.make_token(TokenType::Comma),
-                '+' => self.make_token(TokenType::Plus),
-                '*' => self.make_token(TokenType::Star),
-                '/' => self.make_token(TokenType::Slash),
-                
-                // --- 可能的多字符 Token ---
-                '-' => {
-                    if self.peek() == Some('>') {
-                        self.advance(); // 消耗 '>'
-                        self.make_token(TokenType::Arrow)
-                    } else {
-                        self.make_token(TokenType::Minus)
+        // 非 trivia 模式下注释要被跳过，用循环而不是递归调用自己来实现：
+        // 源码可能是一长串挨在一起的注释（中间没有别的 token），递归会为每个
+        // 注释都压一层栈帧，足够多的注释就会栈溢出。
+        loop {
+            // 1. 准备：跳过空白
+            self.skip_whitespace();
+
+            // 2. 标记起点
+            self.start_pos = self.current_pos;
+            self.start_line = self.line;
+            self.start_column = self.column;
+
+            // 3. 识别与分派
+            // 先用一张 256 项的查找表给 ASCII 字节的第一个字符归类，只有非 ASCII
+            // （多字节 UTF-8 字符）才会落到较慢的 `is_alphabetic` 判断上。
+            let token = if let Some(c) = self.advance() {
+                if c.is_ascii() {
+                    match dispatch::classify(c as u8) {
+                        ByteClass::Whitespace => {
+                            unreachable!("skip_whitespace 应该已经消费掉了所有 ASCII 空白")
+                        }
+                        // 行列号已经在 advance() 消耗这个 '\n' 时推进过了。
+                        ByteClass::Newline => self.make_token(TokenType::Newline),
+                        ByteClass::Quote => self.scan_string(),
+                        ByteClass::Digit => self.scan_number(),
+                        ByteClass::IdentStart => self.scan_identifier(),
+                        ByteClass::Slash => self.scan_slash(),
+                        ByteClass::Punct => self.scan_punct(c),
+                        ByteClass::Illegal => self.make_token(TokenType::Illegal),
                     }
+                } else if c.is_alphabetic() {
+                    self.scan_identifier()
+                } else {
+                    self.make_token(TokenType::Illegal)
                 }
+            } else {
+                // 4. 文件末尾
+                let span = Span {
+                    file_id: self.file_id,
+                    start: self.current_pos,
+                    end: self.current_pos,
+                };
+                Token {
+                    kind: TokenType::Eof,
+                    lexeme: "".to_string(),
+                    span,
+                    line: self.line,
+                    column: self.column,
+                }
+            };
 
-                ':' => {
-                    if self.peek() == Some(':') {
-                        self.advance(); // 消耗第二个 ':'
-                        self.make_token(TokenType::DoubleColon)
-                    } else {
-                        self.make_token(TokenType::Colon)
-                    }
-                },
-                
-                // --- 换行符 ---
-                '\n' => {
-                    let token = self.make_token(TokenType::Newline);
-                    self.line += 1;
-                    self.column = 1; // 新的一行，列号重置为 1
-                    token
+            if token.kind == TokenType::Comment && !self.emit_trivia {
+                continue;
+            }
+            return token;
+        }
+    }
+
+    /// 消耗一个已经确认属于 `ByteClass::Slash` 的字符：要么是注释的开头，要么就是单纯的除号。
+    fn scan_slash(&mut self) -> Token {
+        if self.peek() == Some('/') {
+            self.advance(); // 消耗第二个 '/'
+            self.scan_line_comment()
+        } else if self.peek() == Some('*') {
+            self.advance(); // 消耗 '*'
+            self.scan_block_comment()
+        } else {
+            self.make_token(TokenType::Slash)
+        }
+    }
+
+    /// 分派一个已经确认属于 `ByteClass::Punct` 的字符。单字符 token 直接返回，
+    /// 可能构成多字符 token 的再往后多看一眼。
+    fn scan_punct(&mut self, c: char) -> Token {
+        match c {
+            '{' => self.make_token(TokenType::LeftBrace),
+            '}' => self.make_token(TokenType::RightBrace),
+            '(' => self.make_token(TokenType::LeftParen),
+            ')' => self.make_token(TokenType::RightParen),
+            '^' => self.make_token(TokenType::Caret),
+            '&' => self.make_token(TokenType::Ampersand),
+            '.' => self.make_token(TokenType::Dot),
+            ',' => self.make_token(TokenType::Comma),
+            '+' => self.make_token(TokenType::Plus),
+            '*' => self.make_token(TokenType::Star),
+
+            '-' => {
+                if self.peek() == Some('>') {
+                    self.advance(); // 消耗 '>'
+                    self.make_token(TokenType::Arrow)
+                } else {
+                    self.make_token(TokenType::Minus)
                 }
+            }
 
-                // --- 复杂模式：分派给专门的扫描函数 ---
-                '"' => self.scan_string(),
-                c if c.is_ascii_digit() => self.scan_number(),
-                c if c.is_alphabetic() || c == '_' => self.scan_identifier(),
+            ':' => {
+                if self.peek() == Some(':') {
+                    self.advance(); // 消耗第二个 ':'
+                    self.make_token(TokenType::DoubleColon)
+                } else {
+                    self.make_token(TokenType::Colon)
+                }
+            }
 
-                // 单独捕获分号
-                ';' => {
-                    // 当我们捕获到分号时...
-                    let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
-                    let err = LexerError::new(LexerErrorKind::UnnecessarySemicolon, err_span);
-                    self.diagnostics.add_error(CompilerError::Lexer(err));
-                    self.make_token(TokenType::Illegal)
+            // --- 比较与逻辑操作符：都用同样的“二字符前瞻”模式 ---
+            '=' => {
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.make_token(TokenType::EqualEqual)
+                } else {
+                    self.make_token(TokenType::Equal)
                 }
-                // --- 未知字符 ---
-                _ => self.make_token(TokenType::Illegal),
             }
-        } else {
-            // 4. 文件末尾
-            let span = Span {
-                file_id: self.file_id,
-                start: self.current_pos,
-                end: self.current_pos,
-            };
-            Token {
-                kind: TokenType::Eof,
-                lexeme: "".to_string(),
-                span,
+            '!' => {
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.make_token(TokenType::BangEqual)
+                } else {
+                    self.make_token(TokenType::Bang)
+                }
+            }
+            '<' => {
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.make_token(TokenType::LessEqual)
+                } else {
+                    self.make_token(TokenType::Less)
+                }
+            }
+            '>' => {
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.make_token(TokenType::GreaterEqual)
+                } else {
+                    self.make_token(TokenType::Greater)
+                }
             }
+
+            // 单独捕获分号：nyanc 里语句不需要分号结尾，写了就报一个诊断
+            ';' => {
+                let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
+                let err = LexerError::new(LexerErrorKind::UnnecessarySemicolon, err_span);
+                self.report_error(err);
+                self.make_token(TokenType::Illegal)
+            }
+
+            _ => unreachable!("dispatch::classify 保证了 ByteClass::Punct 只会对应上面列出的字符"),
         }
     }
 
@@ -172,46 +301,166 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    /// 扫描一个完整的数字字面量，可以是整数或浮点数
+    /// 扫描一个完整的数字字面量：十进制/十六进制/二进制/八进制整数，或带指数的浮点数。
+    /// 词素（lexeme）保留原始文本（含前缀、下划线分隔符等），交给后续阶段去解码数值。
     fn scan_number(&mut self) -> Token {
-        // 1. 扫描整数部分
+        // 第一个数字已经在 next_token 的分派里被消耗了，这里看它是不是单独的 '0'，
+        // 从而判断后面是不是 0x/0b/0o 这样的进制前缀。
+        if &self.source[self.start_pos..self.current_pos] == "0" {
+            if let Some(radix_char @ ('x' | 'X' | 'b' | 'B' | 'o' | 'O')) = self.peek() {
+                return self.scan_radix_number(radix_char);
+            }
+        }
+        self.scan_decimal_number()
+    }
+
+    /// 扫描 `0x`/`0b`/`0o` 前缀的整数字面量。
+    fn scan_radix_number(&mut self, radix_char: char) -> Token {
+        self.advance(); // 消耗 'x'/'b'/'o'
+        let is_radix_digit: fn(char) -> bool = match radix_char {
+            'x' | 'X' => |c| c.is_ascii_hexdigit(),
+            'b' | 'B' => |c| c == '0' || c == '1',
+            'o' | 'O' => |c| ('0'..='7').contains(&c),
+            _ => unreachable!("调用方已经保证 radix_char 只能是 x/X/b/B/o/O"),
+        };
+
+        let (digit_count, separators_ok) = self.scan_digit_run(false, is_radix_digit);
+        if digit_count == 0 || !separators_ok {
+            self.report_malformed_number();
+            return self.make_token(TokenType::Illegal);
+        }
+        self.make_token(TokenType::Integer)
+    }
+
+    /// 扫描十进制整数/浮点数，支持下划线分隔符和 `e`/`E` 科学计数法指数。
+    fn scan_decimal_number(&mut self) -> Token {
+        // next_token 的分派在调用 scan_number 之前已经消耗了整数部分的第一个数字，
+        // 这里传 `true` 告诉 scan_digit_run 不要把紧跟着的 '_' 误判成开头的分隔符。
+        let (_, mut separators_ok) = self.scan_digit_run(true, |c| c.is_ascii_digit());
+
+        let mut is_float = false;
+        // 小数部分：需要 '.' 后面紧跟一个数字，否则 '.' 属于别的 token（比如成员访问）
+        if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            is_float = true;
+            self.advance(); // 消耗 '.'
+            let (_, frac_ok) = self.scan_digit_run(false, |c| c.is_ascii_digit());
+            separators_ok &= frac_ok;
+        }
+
+        // 指数部分：'e'/'E'，可选的 '+'/'-'，后面至少跟一个数字
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let sign_offset = if matches!(self.peek_nth(1), Some('+' | '-')) { 2 } else { 1 };
+            if self.peek_nth(sign_offset).is_some_and(|c| c.is_ascii_digit()) {
+                is_float = true;
+                self.advance(); // 消耗 'e'/'E'
+                if sign_offset == 2 {
+                    self.advance(); // 消耗符号
+                }
+                let (_, exp_ok) = self.scan_digit_run(false, |c| c.is_ascii_digit());
+                separators_ok &= exp_ok;
+            }
+        }
+
+        if !separators_ok {
+            self.report_malformed_number();
+            return self.make_token(TokenType::Illegal);
+        }
+
+        if is_float {
+            self.make_token(TokenType::Float)
+        } else {
+            self.make_token(TokenType::Integer)
+        }
+    }
+
+    /// 扫描一串满足 `is_digit` 的数字，允许用 `_` 作为分隔符。
+    /// `leading_digit_consumed` 告诉它调用方是否已经在进入这个数字片段之前消耗了一个数字
+    /// （比如 `next_token` 的分派在调用 `scan_number` 之前就吃掉了整数部分的第一位），
+    /// 否则紧跟在那个数字后面的 `_` 会被误判成出现在开头的非法分隔符。
+    /// 返回扫描到的数字个数（不含下划线，也不含 `leading_digit_consumed` 计入的那一个），
+    /// 以及分隔符的使用是否合法（不能出现在开头、结尾，也不能连续出现两个）。
+    fn scan_digit_run(&mut self, leading_digit_consumed: bool, is_digit: fn(char) -> bool) -> (usize, bool) {
+        let mut digit_count = 0;
+        let mut last_was_underscore = false;
+        let mut saw_digit = leading_digit_consumed;
+        let mut separators_ok = true;
         while let Some(c) = self.peek() {
-            if c.is_ascii_digit() {
+            if is_digit(c) {
                 self.advance();
+                digit_count += 1;
+                saw_digit = true;
+                last_was_underscore = false;
+            } else if c == '_' {
+                if !saw_digit || last_was_underscore {
+                    separators_ok = false;
+                }
+                self.advance();
+                last_was_underscore = true;
             } else {
                 break;
             }
         }
+        if last_was_underscore {
+            separators_ok = false; // 末尾的分隔符同样非法
+        }
+        (digit_count, separators_ok)
+    }
 
-        // 2. 检查是否是浮点数
-        // 我们需要预读两位：一个 '.' 和它后面的一个数字
-        let mut is_float = false;
-        if self.peek() == Some('.') {
-            // 创建一个临时的克隆迭代器来预读两位
-            let mut ahead = self.chars.clone();
-            ahead.next(); // 跳过 '.'
-            if let Some(next_c) = ahead.next() {
-                if next_c.is_ascii_digit() {
-                    is_float = true;
-                    self.advance(); // 确认是浮点数，消耗 '.'
-
-                    // 3. 扫描小数部分
-                    while let Some(c) = self.peek() {
-                        if c.is_ascii_digit() {
-                            self.advance();
-                        } else {
+    /// 工具函数：以当前已扫描的数字字面量范围上报一个 `MalformedNumber` 错误。
+    fn report_malformed_number(&mut self) {
+        let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
+        let err = LexerError::new(LexerErrorKind::MalformedNumber, err_span);
+        self.report_error(err);
+    }
+
+    /// 扫描 `//` 行注释，直到行尾（不包含 `\n`）。是否跳过这个 Comment token
+    /// 由调用方（`next_token` 的循环）决定，这里只负责把字符吃掉。
+    fn scan_line_comment(&mut self) -> Token {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.advance();
+        }
+        self.make_token(TokenType::Comment)
+    }
+
+    /// 扫描 `/* ... */` 块注释，支持嵌套。
+    fn scan_block_comment(&mut self) -> Token {
+        let mut depth = 1u32;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.advance();
+                    if self.peek() == Some('/') {
+                        self.advance();
+                        depth -= 1;
+                        if depth == 0 {
                             break;
                         }
                     }
                 }
+                Some('/') => {
+                    self.advance();
+                    if self.peek() == Some('*') {
+                        self.advance();
+                        depth += 1;
+                    }
+                }
+                Some(_) => {
+                    self.advance();
+                }
+                None => {
+                    let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
+                    let err = LexerError::new(LexerErrorKind::UnterminatedComment, err_span);
+                    self.report_error(err);
+                    return self.make_token(TokenType::Illegal);
+                }
             }
         }
-        
-        if is_float {
-            self.make_token(TokenType::Float)
-        } else {
-            self.make_token(TokenType::Integer)
-        }
+
+        // 是否跳过这个 Comment token 由调用方（`next_token` 的循环）决定。
+        self.make_token(TokenType::Comment)
     }
 
     /// 扫描一个完整的字符串字面量，支持多行和转义字符。
@@ -230,17 +479,11 @@ impl<'a> Lexer<'a> {
                     // 错误应该从字符串的起始位置（self.start_pos）到当前位置
                     let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
                     let err = LexerError::new(LexerErrorKind::UnterminatedString, err_span);
-                    self.diagnostics.add_error(CompilerError::Lexer(err));
+                    self.report_error(err);
 
                     // 返回一个 Illegal Token，让编译器知道这里出了问题
                     return self.make_token(TokenType::Illegal);
                 }
-                // --- 换行符：支持多行字符串 ---
-                Some('\n') => {
-                    self.advance();
-                    self.line += 1;
-                    self.column = 1;
-                }
                 // --- 转义字符处理 ---
                 Some('\\') => {
                     self.advance(); // 消耗 '\'
@@ -257,13 +500,13 @@ impl<'a> Lexer<'a> {
                             self.advance(); // 消耗这个无效字符
                             let err_span = Span { file_id: self.file_id, start: err_start, end: self.current_pos };
                             let err = LexerError::new(LexerErrorKind::InvalidEscapeSequence(c), err_span);
-                            self.diagnostics.add_error(CompilerError::Lexer(err));
+                            self.report_error(err);
                         }
                         None => {
                             // '\' 后面直接是文件结尾，也属于未闭合
                             let err_span = Span { file_id: self.file_id, start: self.start_pos, end: self.current_pos };
                             let err = LexerError::new(LexerErrorKind::UnterminatedString, err_span);
-                            self.diagnostics.add_error(CompilerError::Lexer(err));
+                            self.report_error(err);
                             return self.make_token(TokenType::Illegal);
                         }
                     }
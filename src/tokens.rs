@@ -5,6 +5,10 @@ pub struct Token {
     pub kind: TokenType,
     pub lexeme: String,
     pub span: Span,
+    /// Token 起始字符所在的行号 (从 1 开始)
+    pub line: u32,
+    /// Token 起始字符所在的列号 (从 1 开始)
+    pub column: u32,
 }
 
 // TokenType 枚举，轻量且可复制
@@ -16,7 +20,15 @@ pub enum TokenType {
     LeftParen,
     RightParen,
     Colon,
+    DoubleColon,
     Equal,
+    EqualEqual,
+    Bang,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
     Arrow,
     Caret,
     Ampersand,
@@ -30,7 +42,12 @@ pub enum TokenType {
     // --- 字面量 ---
     Identifier,
     Integer,
+    Float,
     String,
+    Bool,
+
+    // --- 琐碎 Token（trivia，默认跳过，仅在 `with_trivia` 开启时产出） ---
+    Comment,
 
     // --- 关键字 ---
     Let,